@@ -1,12 +1,18 @@
 use crate::error::{IdentityError, ShareAddressError};
 use data_encoding::BASE32_NOPAD;
-use ed25519_dalek::Keypair;
+use ed25519_dalek::{Keypair, PublicKey};
 use rand::rngs::OsRng;
-use std::fmt::{self};
+use std::{fmt::{self}, str::FromStr};
 
+/// An identity that may or may not carry its own secret key material.
+///
+/// `keypair` is `Some` for identities generated or imported locally (so the
+/// holder can sign documents) and `None` for identities reconstructed from an
+/// address string received from a peer, where only the public key is known.
 pub struct Identity {
     pub shortname: String,
-    pub keypair: Keypair,
+    pub keypair: Option<Keypair>,
+    pub public: PublicKey,
 }
 
 impl fmt::Display for Identity {
@@ -15,13 +21,44 @@ impl fmt::Display for Identity {
             f,
             "@{}.b{}",
             self.shortname,
-            BASE32_NOPAD.encode(self.keypair.public.as_bytes())
+            BASE32_NOPAD.encode(self.public.as_bytes())
         )
     }
 }
 
 impl Identity {
     pub fn new(shortname: String, keypair: Option<Keypair>) -> Result<Self, IdentityError> {
+        Identity::validate_shortname(&shortname)?;
+        let keypair = match keypair {
+            Some(kp) => kp,
+            None => {
+                let mut csprng = OsRng {};
+                Keypair::generate(&mut csprng)
+            }
+        };
+        let public = keypair.public;
+        Ok(Self {
+            shortname,
+            keypair: Some(keypair),
+            public,
+        })
+    }
+
+    /// Builds an identity from a public key alone, with no secret material.
+    ///
+    /// Used to represent an identity learned from another peer (for example
+    /// from a received document or a parsed address string), which can be
+    /// checked against but never signed with.
+    pub fn from_public(shortname: String, public: PublicKey) -> Result<Self, IdentityError> {
+        Identity::validate_shortname(&shortname)?;
+        Ok(Self {
+            shortname,
+            keypair: None,
+            public,
+        })
+    }
+
+    fn validate_shortname(shortname: &str) -> Result<(), IdentityError> {
         if !(shortname.len() >= 1 && shortname.len() < 16) {
             return Err(IdentityError::InvalidLength);
         }
@@ -34,20 +71,44 @@ impl Identity {
         if shortname.chars().nth(0).unwrap_or('0').is_ascii_digit() {
             return Err(IdentityError::StartsWithDigit);
         }
-        let keypair = match keypair {
-            Some(kp) => kp,
-            None => {
-                let mut csprng = OsRng {};
-                Keypair::generate(&mut csprng)
-            }
-        };
-        Ok(Self { shortname, keypair })
+        Ok(())
+    }
+}
+
+impl FromStr for Identity {
+    type Err = IdentityError;
+
+    /// Parses an identity address of the form `@shortname.b<base32pubkey>`,
+    /// as produced by `Display`. The resulting identity holds only the
+    /// public key, since an address string never carries secret material.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rest = s.strip_prefix('@').ok_or(IdentityError::MissingSigil)?;
+        let (shortname, encoded_public) =
+            rest.split_once('.').ok_or(IdentityError::InvalidBase32)?;
+        let encoded_public = encoded_public
+            .strip_prefix('b')
+            .ok_or(IdentityError::InvalidBase32)?;
+        let public_bytes = BASE32_NOPAD
+            .decode(encoded_public.as_bytes())
+            .map_err(|_| IdentityError::InvalidBase32)?;
+        if public_bytes.len() != 32 {
+            return Err(IdentityError::InvalidKeyLength);
+        }
+        let public =
+            PublicKey::from_bytes(&public_bytes).map_err(|_| IdentityError::InvalidKeyLength)?;
+        Identity::from_public(shortname.to_owned(), public)
     }
 }
 
+/// A share address that may or may not carry its own secret key material.
+///
+/// `keypair` is `Some` for shares created or imported locally and `None` for
+/// shares reconstructed from an address string received from a peer, where
+/// only the public key is known.
 pub struct ShareAddress {
     pub name: String,
-    pub keypair: Keypair,
+    pub keypair: Option<Keypair>,
+    pub public: PublicKey,
 }
 
 impl fmt::Display for ShareAddress {
@@ -56,13 +117,44 @@ impl fmt::Display for ShareAddress {
             f,
             "+{}.b{}",
             self.name,
-            BASE32_NOPAD.encode(self.keypair.public.as_bytes())
+            BASE32_NOPAD.encode(self.public.as_bytes())
         )
     }
 }
 
 impl ShareAddress {
     pub fn new(name: String, keypair: Option<Keypair>) -> Result<Self, ShareAddressError> {
+        ShareAddress::validate_name(&name)?;
+        let keypair = match keypair {
+            Some(kp) => kp,
+            None => {
+                let mut csprng = OsRng {};
+                Keypair::generate(&mut csprng)
+            }
+        };
+        let public = keypair.public;
+        Ok(Self {
+            name,
+            keypair: Some(keypair),
+            public,
+        })
+    }
+
+    /// Builds a share address from a public key alone, with no secret material.
+    ///
+    /// Used to represent a share learned from another peer (for example from
+    /// a received document or a parsed address string), which can be checked
+    /// against but never signed with.
+    pub fn from_public(name: String, public: PublicKey) -> Result<Self, ShareAddressError> {
+        ShareAddress::validate_name(&name)?;
+        Ok(Self {
+            name,
+            keypair: None,
+            public,
+        })
+    }
+
+    fn validate_name(name: &str) -> Result<(), ShareAddressError> {
         if !(name.len() >= 1 && name.len() < 16) {
             return Err(ShareAddressError::InvalidLength);
         }
@@ -75,13 +167,111 @@ impl ShareAddress {
         if name.chars().nth(0).unwrap_or('0').is_ascii_digit() {
             return Err(ShareAddressError::StartsWithDigit);
         }
-        let keypair = match keypair {
-            Some(kp) => kp,
-            None => {
-                let mut csprng = OsRng {};
-                Keypair::generate(&mut csprng)
-            }
-        };
-        Ok(Self { name, keypair })
+        Ok(())
+    }
+}
+
+impl FromStr for ShareAddress {
+    type Err = ShareAddressError;
+
+    /// Parses a share address of the form `+name.b<base32pubkey>`, as
+    /// produced by `Display`. The resulting share address holds only the
+    /// public key, since an address string never carries secret material.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rest = s.strip_prefix('+').ok_or(ShareAddressError::MissingSigil)?;
+        let (name, encoded_public) = rest
+            .split_once('.')
+            .ok_or(ShareAddressError::InvalidBase32)?;
+        let encoded_public = encoded_public
+            .strip_prefix('b')
+            .ok_or(ShareAddressError::InvalidBase32)?;
+        let public_bytes = BASE32_NOPAD
+            .decode(encoded_public.as_bytes())
+            .map_err(|_| ShareAddressError::InvalidBase32)?;
+        if public_bytes.len() != 32 {
+            return Err(ShareAddressError::InvalidKeyLength);
+        }
+        let public = PublicKey::from_bytes(&public_bytes)
+            .map_err(|_| ShareAddressError::InvalidKeyLength)?;
+        ShareAddress::from_public(name.to_owned(), public)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identity_display_then_parse_round_trips() {
+        let identity = Identity::new("alice".to_owned(), None).unwrap();
+        let parsed: Identity = identity.to_string().parse().unwrap();
+        assert_eq!(parsed.shortname, identity.shortname);
+        assert_eq!(parsed.public.as_bytes(), identity.public.as_bytes());
+        assert!(parsed.keypair.is_none());
+    }
+
+    #[test]
+    fn identity_parse_rejects_missing_sigil() {
+        let identity = Identity::new("alice".to_owned(), None).unwrap();
+        let address = identity.to_string();
+        let without_sigil = address.strip_prefix('@').unwrap();
+        assert!(matches!(
+            without_sigil.parse::<Identity>(),
+            Err(IdentityError::MissingSigil)
+        ));
+    }
+
+    #[test]
+    fn identity_parse_rejects_invalid_base32() {
+        assert!(matches!(
+            "@alice.b1".parse::<Identity>(),
+            Err(IdentityError::InvalidBase32)
+        ));
+    }
+
+    #[test]
+    fn identity_parse_rejects_invalid_key_length() {
+        let short_key = BASE32_NOPAD.encode(&[0_u8; 16]);
+        assert!(matches!(
+            format!("@alice.b{short_key}").parse::<Identity>(),
+            Err(IdentityError::InvalidKeyLength)
+        ));
+    }
+
+    #[test]
+    fn share_address_display_then_parse_round_trips() {
+        let share = ShareAddress::new("garden".to_owned(), None).unwrap();
+        let parsed: ShareAddress = share.to_string().parse().unwrap();
+        assert_eq!(parsed.name, share.name);
+        assert_eq!(parsed.public.as_bytes(), share.public.as_bytes());
+        assert!(parsed.keypair.is_none());
+    }
+
+    #[test]
+    fn share_address_parse_rejects_missing_sigil() {
+        let share = ShareAddress::new("garden".to_owned(), None).unwrap();
+        let address = share.to_string();
+        let without_sigil = address.strip_prefix('+').unwrap();
+        assert!(matches!(
+            without_sigil.parse::<ShareAddress>(),
+            Err(ShareAddressError::MissingSigil)
+        ));
+    }
+
+    #[test]
+    fn share_address_parse_rejects_invalid_base32() {
+        assert!(matches!(
+            "+garden.b1".parse::<ShareAddress>(),
+            Err(ShareAddressError::InvalidBase32)
+        ));
+    }
+
+    #[test]
+    fn share_address_parse_rejects_invalid_key_length() {
+        let short_key = BASE32_NOPAD.encode(&[0_u8; 16]);
+        assert!(matches!(
+            format!("+garden.b{short_key}").parse::<ShareAddress>(),
+            Err(ShareAddressError::InvalidKeyLength)
+        ));
     }
 }
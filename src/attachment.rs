@@ -0,0 +1,94 @@
+use data_encoding::BASE32_NOPAD;
+use sha2::{Digest, Sha256};
+use std::io::{self, Read};
+
+/// Read buffer size used by `Attachment::from_reader`; attachments are
+/// streamed in chunks this large rather than being read fully into memory.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// A computed attachment hash and size, streamed from a `Read` rather than
+/// built from bytes already held in memory.
+pub struct Attachment {
+    hash: String,
+    size: i32,
+}
+
+impl Attachment {
+    /// The `b`-prefixed, 53-character Base32 (RFC 4648, no padding) SHA-256
+    /// hash of the attachment, matching `Document::attachment_hash`.
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    /// The attachment's size in bytes, matching `Document::attachment_size`.
+    pub fn size(&self) -> i32 {
+        self.size
+    }
+
+    /// Streams `reader` to completion in fixed-size chunks, hashing each
+    /// chunk and tallying the total byte count, without ever holding the
+    /// whole attachment in memory.
+    pub fn from_reader<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut hasher = Sha256::new();
+        let mut buffer = [0_u8; CHUNK_SIZE];
+        let mut size: i64 = 0;
+        loop {
+            let read = reader.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+            size += read as i64;
+        }
+        Ok(Self {
+            hash: format!("b{}", BASE32_NOPAD.encode(&hasher.finalize())),
+            size: size_to_i32(size)?,
+        })
+    }
+}
+
+/// Converts a streamed byte count to `Document::attachment_size`'s `i32`,
+/// erroring rather than silently clamping when the attachment is larger than
+/// `i32::MAX` bytes, so the reported size never disagrees with the bytes
+/// actually hashed.
+fn size_to_i32(size: i64) -> io::Result<i32> {
+    size.try_into().map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "attachment is larger than i32::MAX bytes",
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_and_sizes_a_known_plaintext() {
+        let attachment = Attachment::from_reader("hello world".as_bytes()).unwrap();
+        assert_eq!(attachment.size(), 11);
+        assert_eq!(
+            attachment.hash(),
+            "bXFGSPOMTJU7ARJJOKLL5U7NL7LCIJ37DPJJYB3UQRD32ZYXPZXUQ"
+        );
+    }
+
+    #[test]
+    fn hashes_data_spanning_multiple_chunks() {
+        let data = vec![7_u8; CHUNK_SIZE + 123];
+        let attachment = Attachment::from_reader(data.as_slice()).unwrap();
+        assert_eq!(attachment.size() as usize, data.len());
+
+        let mut hasher = Sha256::new();
+        hasher.update(&data);
+        let expected = format!("b{}", BASE32_NOPAD.encode(&hasher.finalize()));
+        assert_eq!(attachment.hash(), expected);
+    }
+
+    #[test]
+    fn size_to_i32_errors_instead_of_clamping_oversized_attachments() {
+        assert_eq!(size_to_i32(i64::from(i32::MAX)).unwrap(), i32::MAX);
+        assert!(size_to_i32(i64::from(i32::MAX) + 1).is_err());
+    }
+}
@@ -0,0 +1,307 @@
+use crate::{address::Identity, error::CapabilityError};
+use data_encoding::BASE32_NOPAD;
+use ed25519_dalek::{PublicKey, Signature, Signer, Verifier};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A UCAN-style delegation of write access, letting `issuer` grant `audience`
+/// the right to write under `path_prefix` until `not_after`.
+///
+/// A capability with `proof: None` is a root capability, self-attesting that
+/// `issuer` owns `path_prefix` (exactly as owning `~@shortname` today is
+/// nothing more than an identity asserting its own shortname) — `verify_chain`
+/// enforces this by checking that `path_prefix` actually falls under the
+/// root's own `issuer_shortname`, so a root can only ever claim its own
+/// identity's prefix, never someone else's. A capability with
+/// `proof: Some(parent)` re-delegates a right `issuer` itself holds as
+/// `parent`'s audience; `verify_chain` walks `proof` back to that root and
+/// checks every link along the way.
+pub struct WriteCapability {
+    pub issuer: PublicKey,
+    pub issuer_shortname: String,
+    pub audience: PublicKey,
+    pub path_prefix: String,
+    pub not_after: SystemTime,
+    pub proof: Option<Box<WriteCapability>>,
+    pub signature: String,
+}
+
+impl WriteCapability {
+    /// Issues a new capability, signing it with `issuer`'s secret key.
+    /// `issuer_shortname` is always taken from `issuer.shortname` (never a
+    /// caller-supplied value), so a minted capability can only ever
+    /// self-attest ownership of its *own* identity's shortname.
+    pub fn new(
+        issuer: &Identity,
+        audience: PublicKey,
+        path_prefix: String,
+        not_after: SystemTime,
+        proof: Option<Box<WriteCapability>>,
+    ) -> Result<Self, CapabilityError> {
+        let keypair = issuer
+            .keypair
+            .as_ref()
+            .ok_or(CapabilityError::MissingSecretKey)?;
+
+        let mut capability = Self {
+            issuer: issuer.public,
+            issuer_shortname: issuer.shortname.clone(),
+            audience,
+            path_prefix,
+            not_after,
+            proof,
+            signature: String::new(),
+        };
+        let signature = keypair.sign(capability.canonical_encoding().as_bytes());
+        capability.signature = format!("b{}", BASE32_NOPAD.encode(&signature.to_bytes()));
+        Ok(capability)
+    }
+
+    /// The canonical `key\tvalue\n` encoding signed by `issuer`.
+    pub fn canonical_encoding(&self) -> String {
+        format!(
+            "audience\tb{}\nissuer\tb{}\nissuer_shortname\t{}\nnot_after\t{}\npath_prefix\t{}\n",
+            BASE32_NOPAD.encode(self.audience.as_bytes()),
+            BASE32_NOPAD.encode(self.issuer.as_bytes()),
+            self.issuer_shortname,
+            self.not_after_as_u128(),
+            self.path_prefix
+        )
+    }
+
+    pub fn not_after_as_u128(&self) -> u128 {
+        self.not_after
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_micros()
+    }
+
+    /// Verifies `self.signature` against `self.issuer`.
+    pub fn verify_signature(&self) -> bool {
+        BASE32_NOPAD
+            .decode(self.signature.get(1..).unwrap_or_default().as_bytes())
+            .ok()
+            .and_then(|bytes| Signature::from_bytes(&bytes).ok())
+            .map(|signature| {
+                self.issuer
+                    .verify(self.canonical_encoding().as_bytes(), &signature)
+                    .is_ok()
+            })
+            .unwrap_or_default()
+    }
+
+    /// The public key at the root of this capability's `proof` chain, i.e.
+    /// the identity that originally asserted ownership of `path_prefix`.
+    pub fn root_issuer(&self) -> &PublicKey {
+        match &self.proof {
+            Some(parent) => parent.root_issuer(),
+            None => &self.issuer,
+        }
+    }
+
+    /// The shortname `root_issuer()` self-attested to own when it minted the
+    /// root of this chain.
+    pub fn root_issuer_shortname(&self) -> &str {
+        match &self.proof {
+            Some(parent) => parent.root_issuer_shortname(),
+            None => &self.issuer_shortname,
+        }
+    }
+
+    /// Checks that every link in this capability's chain signs correctly,
+    /// has not expired as of `at`, hands off to the next link's audience
+    /// without a gap, and never re-delegates more than it was itself
+    /// granted: a link can only narrow `path_prefix` and can only bring
+    /// `not_after` earlier than its parent's, never the reverse.
+    fn verify_links(&self, at: SystemTime) -> bool {
+        self.not_after > at
+            && self.verify_signature()
+            && match &self.proof {
+                Some(parent) => {
+                    parent.audience == self.issuer
+                        && self.path_prefix.starts_with(parent.path_prefix.as_str())
+                        && self.not_after <= parent.not_after
+                        && parent.verify_links(at)
+                }
+                None => true,
+            }
+    }
+
+    /// Checks that this capability (and its proof chain) authorizes
+    /// `document_author` to write to `document_path` as of `at`: the
+    /// capability's audience must be the document's author, its
+    /// `path_prefix` must cover the document's path, the root of the chain
+    /// must legitimately own the `/~@shortname` prefix it claims, and every
+    /// link in the chain back to the root must verify and not be expired.
+    pub fn verify_chain(
+        &self,
+        document_author: &PublicKey,
+        document_path: &str,
+        at: SystemTime,
+    ) -> bool {
+        let owner_prefix = format!("/~@{}", self.root_issuer_shortname());
+        self.audience == *document_author
+            && document_path.starts_with(self.path_prefix.as_str())
+            && self.path_prefix.starts_with(&owner_prefix)
+            && document_path
+                .strip_prefix(&owner_prefix)
+                .map_or(false, |rest| rest.is_empty() || rest.starts_with('/'))
+            && self.verify_links(at)
+    }
+
+    /// Encodes this capability and its full `proof` chain into a single
+    /// token embeddable in `Document`'s wire format: fields within a link
+    /// are joined by `,` and links by `>` (this capability first, its proof
+    /// next, and so on up to the root). Does not re-verify anything; callers
+    /// should run the decoded chain through `verify_chain`.
+    pub fn encode(&self) -> String {
+        let mut links = Vec::new();
+        let mut current = Some(self);
+        while let Some(capability) = current {
+            links.push(format!(
+                "b{},{},b{},{},{},{}",
+                BASE32_NOPAD.encode(capability.issuer.as_bytes()),
+                capability.issuer_shortname,
+                BASE32_NOPAD.encode(capability.audience.as_bytes()),
+                capability.path_prefix,
+                capability.not_after_as_u128(),
+                capability.signature,
+            ));
+            current = capability.proof.as_deref();
+        }
+        links.join(">")
+    }
+
+    /// Decodes a capability chain produced by `encode`. Does not verify
+    /// anything; callers should run the result through `verify_chain`.
+    pub fn decode(encoded: &str) -> Result<Self, CapabilityError> {
+        let mut capability: Option<Self> = None;
+        for link in encoded.split('>').rev() {
+            let mut fields = link.split(',');
+            let issuer = decode_public_key(fields.next())?;
+            let issuer_shortname = fields.next().ok_or(CapabilityError::Malformed)?.to_owned();
+            let audience = decode_public_key(fields.next())?;
+            let path_prefix = fields.next().ok_or(CapabilityError::Malformed)?.to_owned();
+            let not_after_micros: u64 = fields
+                .next()
+                .and_then(|value| value.parse().ok())
+                .ok_or(CapabilityError::Malformed)?;
+            let signature = fields.next().ok_or(CapabilityError::Malformed)?.to_owned();
+            if fields.next().is_some() {
+                return Err(CapabilityError::Malformed);
+            }
+            capability = Some(Self {
+                issuer,
+                issuer_shortname,
+                audience,
+                path_prefix,
+                not_after: UNIX_EPOCH + Duration::from_micros(not_after_micros),
+                proof: capability.map(Box::new),
+                signature,
+            });
+        }
+        capability.ok_or(CapabilityError::Malformed)
+    }
+}
+
+fn decode_public_key(field: Option<&str>) -> Result<PublicKey, CapabilityError> {
+    let encoded = field
+        .ok_or(CapabilityError::Malformed)?
+        .strip_prefix('b')
+        .ok_or(CapabilityError::Malformed)?;
+    let bytes = BASE32_NOPAD
+        .decode(encoded.as_bytes())
+        .map_err(|_| CapabilityError::Malformed)?;
+    PublicKey::from_bytes(&bytes).map_err(|_| CapabilityError::Malformed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::address::Identity;
+    use std::time::Duration;
+
+    #[test]
+    fn delegate_cannot_broaden_scope_or_lifetime() {
+        let alice = Identity::new("alice".to_owned(), None).unwrap();
+        let eve = Identity::new("eve".to_owned(), None).unwrap();
+
+        let root = WriteCapability::new(
+            &alice,
+            eve.public,
+            "/~@alice/public".to_owned(),
+            SystemTime::now() + Duration::from_secs(3600),
+            None,
+        )
+        .unwrap();
+
+        let escalated = WriteCapability::new(
+            &eve,
+            eve.public,
+            "/~@alice".to_owned(),
+            SystemTime::now() + Duration::from_secs(1_000_000),
+            Some(Box::new(root)),
+        )
+        .unwrap();
+
+        assert!(!escalated.verify_chain(&eve.public, "/~@alice/secret", SystemTime::now()));
+    }
+
+    #[test]
+    fn delegate_within_scope_and_lifetime_verifies() {
+        let alice = Identity::new("alice".to_owned(), None).unwrap();
+        let eve = Identity::new("eve".to_owned(), None).unwrap();
+
+        let root = WriteCapability::new(
+            &alice,
+            eve.public,
+            "/~@alice".to_owned(),
+            SystemTime::now() + Duration::from_secs(3600),
+            None,
+        )
+        .unwrap();
+
+        let bob = Identity::new("bob".to_owned(), None).unwrap();
+        let delegated = WriteCapability::new(
+            &eve,
+            bob.public,
+            "/~@alice/public".to_owned(),
+            SystemTime::now() + Duration::from_secs(1800),
+            Some(Box::new(root)),
+        )
+        .unwrap();
+
+        assert!(delegated.verify_chain(&bob.public, "/~@alice/public/notes", SystemTime::now()));
+    }
+
+    #[test]
+    fn root_capability_cannot_claim_a_shortname_the_issuer_does_not_own() {
+        let mallory = Identity::new("mallory".to_owned(), None).unwrap();
+
+        let forged_root = WriteCapability::new(
+            &mallory,
+            mallory.public,
+            "/~@alice".to_owned(),
+            SystemTime::now() + Duration::from_secs(3600),
+            None,
+        )
+        .unwrap();
+
+        assert!(!forged_root.verify_chain(&mallory.public, "/~@alice/hacked", SystemTime::now()));
+    }
+
+    #[test]
+    fn root_capability_over_issuers_own_shortname_verifies() {
+        let alice = Identity::new("alice".to_owned(), None).unwrap();
+
+        let root = WriteCapability::new(
+            &alice,
+            alice.public,
+            "/~@alice".to_owned(),
+            SystemTime::now() + Duration::from_secs(3600),
+            None,
+        )
+        .unwrap();
+
+        assert!(root.verify_chain(&alice.public, "/~@alice/notes", SystemTime::now()));
+    }
+}
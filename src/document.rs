@@ -1,13 +1,16 @@
 use crate::{
     address::{Identity, ShareAddress},
+    attachment::Attachment,
+    capability::WriteCapability,
     error::DocumentError,
+    format::{default_format, get_format},
 };
 use data_encoding::BASE32_NOPAD;
-use ed25519_dalek::{ed25519::signature::Signature, Signer};
+use ed25519_dalek::{Signature, Signer, Verifier};
 use sha2::{Digest, Sha256};
 use std::{
-    path::Path,
-    time::{SystemTime, UNIX_EPOCH},
+    collections::HashMap,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 pub struct Document {
@@ -23,9 +26,11 @@ pub struct Document {
     pub delete_after: Option<SystemTime>,
     pub attachment_size: Option<i32>,
     pub attachment_hash: Option<String>,
+    pub capabilities: Vec<WriteCapability>,
 }
 
 impl Document {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         author: Identity,
         text: String,
@@ -39,6 +44,7 @@ impl Document {
         delete_after: Option<SystemTime>,
         attachment_size: Option<i32>,
         attachment_hash: Option<String>,
+        capabilities: Option<Vec<WriteCapability>>,
     ) -> Result<Self, DocumentError> {
         let document = Self {
             author,
@@ -53,6 +59,7 @@ impl Document {
             delete_after,
             attachment_size,
             attachment_hash,
+            capabilities: capabilities.unwrap_or_default(),
         };
 
         if !document.validate_text() {
@@ -98,17 +105,283 @@ impl Document {
         Ok(document)
     }
 
+    /// Builds a `Document` with an attachment streamed from `reader`,
+    /// computing `attachment_hash`/`attachment_size` instead of requiring
+    /// the caller to hand-build them (and risk them disagreeing with the
+    /// actual attachment bytes).
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_attachment<R: std::io::Read>(
+        author: Identity,
+        text: String,
+        text_hash: Option<String>,
+        format: String,
+        path: String,
+        signature: String,
+        timestamp: SystemTime,
+        share: ShareAddress,
+        share_signature: String,
+        delete_after: Option<SystemTime>,
+        capabilities: Option<Vec<WriteCapability>>,
+        reader: R,
+    ) -> Result<Self, DocumentError> {
+        let attachment = Attachment::from_reader(reader)?;
+        Document::new(
+            author,
+            text,
+            text_hash,
+            format,
+            path,
+            signature,
+            timestamp,
+            share,
+            share_signature,
+            delete_after,
+            Some(attachment.size()),
+            Some(attachment.hash().to_owned()),
+            capabilities,
+        )
+    }
+
+    /// Builds and signs a `Document` locally, computing `signature` and
+    /// `share_signature` from `author`'s and `share`'s own secret keys
+    /// instead of requiring the caller to produce them by hand. This is the
+    /// path a local author should use to produce a document; a document
+    /// received from a peer should instead be checked with `verify`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn sign(
+        author: Identity,
+        text: String,
+        text_hash: Option<String>,
+        format: String,
+        path: String,
+        timestamp: SystemTime,
+        share: ShareAddress,
+        delete_after: Option<SystemTime>,
+        attachment_size: Option<i32>,
+        attachment_hash: Option<String>,
+        capabilities: Option<Vec<WriteCapability>>,
+    ) -> Result<Self, DocumentError> {
+        if author.keypair.is_none() || share.keypair.is_none() {
+            return Err(DocumentError::MissingSecretKey);
+        }
+
+        let unsigned = Self {
+            text_hash: text_hash.unwrap_or_else(|| Document::hash_text(text.clone())),
+            author,
+            text,
+            format,
+            path,
+            signature: String::new(),
+            timestamp,
+            share,
+            share_signature: String::new(),
+            delete_after,
+            attachment_size,
+            attachment_hash,
+            capabilities: capabilities.unwrap_or_default(),
+        };
+
+        let hash = unsigned.hash_document();
+        let signature = format!(
+            "b{}",
+            BASE32_NOPAD.encode(
+                &unsigned
+                    .author
+                    .keypair
+                    .as_ref()
+                    .ok_or(DocumentError::MissingSecretKey)?
+                    .sign(hash.as_bytes())
+                    .to_bytes()
+            )
+        );
+        let share_signature = format!(
+            "b{}",
+            BASE32_NOPAD.encode(
+                &unsigned
+                    .share
+                    .keypair
+                    .as_ref()
+                    .ok_or(DocumentError::MissingSecretKey)?
+                    .sign(hash.as_bytes())
+                    .to_bytes()
+            )
+        );
+
+        let Self {
+            author,
+            text,
+            text_hash,
+            format,
+            path,
+            share,
+            delete_after,
+            attachment_size,
+            attachment_hash,
+            capabilities,
+            ..
+        } = unsigned;
+
+        Document::new(
+            author,
+            text,
+            Some(text_hash),
+            format,
+            path,
+            signature,
+            timestamp,
+            share,
+            share_signature,
+            delete_after,
+            attachment_size,
+            attachment_hash,
+            Some(capabilities),
+        )
+    }
+
+    /// Encodes `self.capabilities` into a single wire-safe value: each
+    /// capability (and its own `proof` chain) via `WriteCapability::encode`,
+    /// joined by `;`. Shared by `serialize` and the default
+    /// `DocumentFormat::canonical_fields` (used by `es.5`), so an `es.5`
+    /// document's signed hash commits to exactly the capabilities
+    /// transmitted; `es.4` does not hash them.
+    pub fn encode_capabilities(&self) -> String {
+        self.capabilities
+            .iter()
+            .map(WriteCapability::encode)
+            .collect::<Vec<_>>()
+            .join(";")
+    }
+
     pub fn hash_document(&self) -> String {
-        let mut hasher = Sha256::new();
-        if self.attachment_hash.is_some() && self.attachment_size.is_some() {
-            hasher.update(format!(
-                "attachment_hash\t{}\nattachment_size\t{}\n",
-                self.attachment_hash.as_ref().unwrap_or(&String::default()),
-                self.attachment_size.as_ref().unwrap_or(&i32::default())
-            ));
+        let format = get_format(&self.format).unwrap_or_else(default_format);
+        let canonical_fields = format
+            .canonical_fields(self)
+            .into_iter()
+            .map(|(key, value)| format!("{key}\t{value}\n"))
+            .collect::<String>();
+        format.hash(canonical_fields.as_bytes())
+    }
+
+    /// The keys `serialize`/`parse` exchange, in the fixed canonical order
+    /// `serialize` writes them in. `attachment_hash`/`attachment_size` are
+    /// only written (and only accepted on parse) together.
+    const WIRE_FIELDS: &'static [&'static str] = &[
+        "attachment_hash",
+        "attachment_size",
+        "author",
+        "capabilities",
+        "delete_after",
+        "format",
+        "path",
+        "share",
+        "share_signature",
+        "signature",
+        "text",
+        "text_hash",
+        "timestamp",
+    ];
+
+    /// Serializes this document to its canonical on-the-wire form: one
+    /// `key\tvalue\n` line per field, in a fixed order, so it can be
+    /// transmitted to and parsed back by another peer. A `delete_after` of
+    /// `0` means the document has none, matching `delete_after_as_u128`.
+    pub fn serialize(&self) -> String {
+        let mut wire = String::new();
+        if let (Some(attachment_hash), Some(attachment_size)) =
+            (&self.attachment_hash, &self.attachment_size)
+        {
+            wire.push_str(&format!("attachment_hash\t{attachment_hash}\n"));
+            wire.push_str(&format!("attachment_size\t{attachment_size}\n"));
         }
-        hasher.update(format!("author\t{}\ndelete_after\t{}\nformat\t{}\npath\t{}\nshare\t{}\nshare_signature\t{}\ntext_hash\t{}\ntimestamp\t{}\n", self.author, self.delete_after_as_u128(), self.format, self.path, self.share, self.share_signature, self.text_hash, self.timestamp_as_u128()));
-        format!("b{}", BASE32_NOPAD.encode(&hasher.finalize()))
+        wire.push_str(&format!("author\t{}\n", self.author));
+        if !self.capabilities.is_empty() {
+            wire.push_str(&format!("capabilities\t{}\n", self.encode_capabilities()));
+        }
+        wire.push_str(&format!("delete_after\t{}\n", self.delete_after_as_u128()));
+        wire.push_str(&format!("format\t{}\n", self.format));
+        wire.push_str(&format!("path\t{}\n", self.path));
+        wire.push_str(&format!("share\t{}\n", self.share));
+        wire.push_str(&format!("share_signature\t{}\n", self.share_signature));
+        wire.push_str(&format!("signature\t{}\n", self.signature));
+        wire.push_str(&format!("text\t{}\n", self.text));
+        wire.push_str(&format!("text_hash\t{}\n", self.text_hash));
+        wire.push_str(&format!("timestamp\t{}\n", self.timestamp_as_u128()));
+        wire
+    }
+
+    /// Parses a document from its canonical on-the-wire form, as produced by
+    /// `serialize`. Every field is routed through the same validators
+    /// `Document::new` runs, so a document received from a peer is checked
+    /// by verifying its signatures against the author's and share's public
+    /// keys, never by re-signing.
+    pub fn parse(wire: &str) -> Result<Self, DocumentError> {
+        let mut fields: HashMap<&str, &str> = HashMap::new();
+        for line in wire.lines() {
+            let (key, value) = line
+                .split_once('\t')
+                .ok_or(DocumentError::MalformedDocument)?;
+            if !Document::WIRE_FIELDS.contains(&key) || fields.insert(key, value).is_some() {
+                return Err(DocumentError::MalformedDocument);
+            }
+        }
+
+        let field = |key: &'static str| fields.get(key).copied().ok_or(DocumentError::MalformedDocument);
+
+        let author: Identity = field("author")?
+            .parse()
+            .map_err(|_| DocumentError::MalformedDocument)?;
+        let share: ShareAddress = field("share")?
+            .parse()
+            .map_err(|_| DocumentError::MalformedDocument)?;
+
+        let timestamp_micros: u64 = field("timestamp")?
+            .parse()
+            .map_err(|_| DocumentError::InvalidTimestamp)?;
+        let timestamp = UNIX_EPOCH + Duration::from_micros(timestamp_micros);
+
+        let delete_after_micros: u64 = field("delete_after")?
+            .parse()
+            .map_err(|_| DocumentError::InvalidDeleteAfter)?;
+        let delete_after = (delete_after_micros != 0)
+            .then(|| UNIX_EPOCH + Duration::from_micros(delete_after_micros));
+
+        let attachment_size = match fields.get("attachment_size") {
+            Some(value) => Some(
+                value
+                    .parse::<i32>()
+                    .map_err(|_| DocumentError::InvalidAttachmentSize)?,
+            ),
+            None => None,
+        };
+        let attachment_hash = fields.get("attachment_hash").map(|value| value.to_string());
+        if attachment_hash.is_some() != attachment_size.is_some() {
+            return Err(DocumentError::MalformedDocument);
+        }
+
+        let capabilities = match fields.get("capabilities") {
+            Some(encoded) => encoded
+                .split(';')
+                .map(WriteCapability::decode)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| DocumentError::MalformedDocument)?,
+            None => Vec::new(),
+        };
+
+        Document::new(
+            author,
+            field("text")?.to_owned(),
+            Some(field("text_hash")?.to_owned()),
+            field("format")?.to_owned(),
+            field("path")?.to_owned(),
+            field("signature")?.to_owned(),
+            timestamp,
+            share,
+            field("share_signature")?.to_owned(),
+            delete_after,
+            attachment_size,
+            attachment_hash,
+            Some(capabilities),
+        )
     }
 
     pub fn hash_text(text: String) -> String {
@@ -136,39 +409,27 @@ impl Document {
     }
 
     pub fn validate_text(&self) -> bool {
-        self.text.as_bytes().len() <= 8000
-            && (self.attachment_hash.is_some()
-                && self.attachment_size.is_some()
-                && !self.text.is_empty()
-                || self.attachment_hash.is_none()
-                    && self.attachment_size.is_none()
-                    && self.text.is_empty())
+        get_format(&self.format)
+            .unwrap_or_else(default_format)
+            .validate_text(self)
     }
 
     pub fn validate_text_hash(&self) -> bool {
-        self.text_hash.chars().nth(0).unwrap_or_default() == 'b'
-            && self.text_hash.len() == 53
-            && BASE32_NOPAD.decode(self.text_hash[1..].as_bytes()).is_ok()
-            && self.text_hash == Document::hash_text(self.text.clone())
+        get_format(&self.format)
+            .unwrap_or_else(default_format)
+            .validate_text_hash(self)
     }
 
     pub fn validate_timestamp(&self) -> bool {
-        let timestamp_int = self.timestamp_as_u128();
-        timestamp_int >= (10 as u128).pow(13) && timestamp_int <= (2 as u128).pow(53) - 2
+        get_format(&self.format)
+            .unwrap_or_else(default_format)
+            .validate_timestamp(self)
     }
 
     pub fn validate_delete_after(&self) -> bool {
-        self.delete_after
-            .map(|delete_after| {
-                let delete_after_int = delete_after
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_micros();
-                delete_after_int >= (10 as u128).pow(13)
-                    && delete_after_int <= (2 as u128).pow(53) - 2
-                    && delete_after > self.timestamp
-            })
-            .unwrap_or_default()
+        get_format(&self.format)
+            .unwrap_or_else(default_format)
+            .validate_delete_after(self)
     }
 
     pub fn validate_format(&self) -> bool {
@@ -178,71 +439,81 @@ impl Document {
     }
 
     pub fn validate_path(&self) -> bool {
-        self.path.is_ascii()
-            && !self.path.contains(|c: char| c.is_ascii_whitespace())
-            && !self.path.contains(|c: char| c.is_ascii_control())
-            && self.path.len() >= 2
-            && self.path.len() <= 512
-            && self.path.chars().nth(0).unwrap_or_default() == '/'
-            && &self.path[0..2] != "/@"
-            && !self.path.contains("//")
-            && ((self.delete_after.is_none() && !self.path.contains("!"))
-                || (self.delete_after.is_some() && self.path.contains("!")))
-            && !self.path.contains("?")
-            && !self.path.contains("#")
-            && !self.path.contains(";")
-            && !self.path.contains("<")
-            && !self.path.contains(">")
-            && !self.path.contains("\"")
-            && !self.path.contains("[")
-            && !self.path.contains("\\")
-            && !self.path.contains("]")
-            && !self.path.contains("^")
-            && !self.path.contains("{")
-            && !self.path.contains("|")
-            && !self.path.contains("}")
-            && (self.attachment_hash.is_some()
-                && self.attachment_size.is_some()
-                && Path::new(&self.path).extension().is_some()
-                || self.attachment_hash.is_none()
-                    && self.attachment_size.is_none()
-                    && Path::new(&self.path).extension().is_none())
-            && (!self.path.contains("~")
-                || self
-                    .path
-                    .contains(&("~@".to_owned() + &self.author.shortname)))
+        get_format(&self.format)
+            .unwrap_or_else(default_format)
+            .validate_path(self)
     }
 
     pub fn validate_signature(&self) -> bool {
+        let format = get_format(&self.format).unwrap_or_else(default_format);
         self.signature.chars().nth(0).unwrap_or_default() == 'b'
             && BASE32_NOPAD.decode(self.signature[1..].as_bytes()).is_ok()
-            && ((self.format == "es.5" && self.signature.len() == 104) || self.format != "es.5")
-            && self.signature
-                == format!(
-                    "b{}",
-                    BASE32_NOPAD.encode(
-                        &self
-                            .author
-                            .keypair
-                            .sign(&self.hash_document().as_bytes())
-                            .as_bytes()
-                    )
-                )
+            && format
+                .signature_length()
+                .map(|length| self.signature.len() == length)
+                .unwrap_or(true)
+            && self.verify_signature()
     }
 
     pub fn validate_share_signature(&self) -> bool {
+        let format = get_format(&self.format).unwrap_or_else(default_format);
         self.share_signature.chars().nth(0).unwrap_or_default() == 'b'
             && BASE32_NOPAD
                 .decode(self.share_signature[1..].as_bytes())
                 .is_ok()
-            && ((self.format == "es.5" && self.share_signature.len() == 104)
-                || self.format != "es.5")
+            && format
+                .signature_length()
+                .map(|length| self.share_signature.len() == length)
+                .unwrap_or(true)
+            && self.verify_share_signature()
+    }
+
+    /// Verifies both the author's and the share's signatures over this
+    /// document against their public keys, without requiring either secret
+    /// key. This is the path a peer should use to check a document it
+    /// received, as opposed to re-signing it locally.
+    pub fn verify(&self) -> bool {
+        self.verify_signature() && self.verify_share_signature()
+    }
+
+    /// Verifies `self.signature` against the author's public key.
+    pub fn verify_signature(&self) -> bool {
+        BASE32_NOPAD
+            .decode(self.signature.get(1..).unwrap_or_default().as_bytes())
+            .ok()
+            .and_then(|bytes| Signature::from_bytes(&bytes).ok())
+            .map(|signature| {
+                self.author
+                    .public
+                    .verify(self.hash_document().as_bytes(), &signature)
+                    .is_ok()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Verifies `self.share_signature` against the share's public key.
+    pub fn verify_share_signature(&self) -> bool {
+        BASE32_NOPAD
+            .decode(self.share_signature.get(1..).unwrap_or_default().as_bytes())
+            .ok()
+            .and_then(|bytes| Signature::from_bytes(&bytes).ok())
+            .map(|signature| {
+                self.share
+                    .public
+                    .verify(self.hash_document().as_bytes(), &signature)
+                    .is_ok()
+            })
+            .unwrap_or_default()
     }
 
     pub fn validate_attachment_size(&self) -> bool {
         self.attachment_size
-            .map(|attachment_size| attachment_size >= 0 && attachment_size <= 2_i32.pow(53) - 2)
-            .unwrap_or_default()
+            .map(|attachment_size| {
+                attachment_size >= 0 && i64::from(attachment_size) <= 2_i64.pow(53) - 2
+            })
+            // `attachment_size` is optional: a document without an attachment is
+            // trivially valid here.
+            .unwrap_or(true)
     }
 
     pub fn validate_attachment_hash(&self) -> bool {
@@ -253,6 +524,243 @@ impl Document {
                     && attachment_hash.len() == 53
                     && BASE32_NOPAD.decode(attachment_hash[1..].as_bytes()).is_ok()
             })
-            .unwrap_or_default()
+            // `attachment_hash` is optional: a document without an attachment is
+            // trivially valid here.
+            .unwrap_or(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::Signer;
+
+    fn signed_document(text: String, path: String) -> Document {
+        let author = Identity::new("alice".to_owned(), None).unwrap();
+        let share = ShareAddress::new("garden".to_owned(), None).unwrap();
+        let mut document = Document {
+            text_hash: Document::hash_text(text.clone()),
+            author,
+            text,
+            format: "es.5".to_owned(),
+            path,
+            signature: String::new(),
+            timestamp: SystemTime::now(),
+            share,
+            share_signature: String::new(),
+            delete_after: None,
+            attachment_size: None,
+            attachment_hash: None,
+            capabilities: Vec::new(),
+        };
+        let hash = document.hash_document();
+        document.signature = format!(
+            "b{}",
+            BASE32_NOPAD.encode(
+                &document
+                    .author
+                    .keypair
+                    .as_ref()
+                    .unwrap()
+                    .sign(hash.as_bytes())
+                    .to_bytes()
+            )
+        );
+        document.share_signature = format!(
+            "b{}",
+            BASE32_NOPAD.encode(
+                &document
+                    .share
+                    .keypair
+                    .as_ref()
+                    .unwrap()
+                    .sign(hash.as_bytes())
+                    .to_bytes()
+            )
+        );
+        document
+    }
+
+    #[test]
+    fn absent_delete_after_and_attachment_fields_validate() {
+        let document = signed_document(String::new(), "/hello".to_owned());
+        assert!(document.validate_delete_after());
+        assert!(document.validate_attachment_size());
+        assert!(document.validate_attachment_hash());
+    }
+
+    #[test]
+    fn large_attachment_size_does_not_panic() {
+        let mut document = signed_document(String::new(), "/hello".to_owned());
+        document.attachment_size = Some(i32::MAX);
+        assert!(document.validate_attachment_size());
+    }
+
+    #[test]
+    fn sign_produces_a_document_that_verifies() {
+        let author = Identity::new("alice".to_owned(), None).unwrap();
+        let share = ShareAddress::new("garden".to_owned(), None).unwrap();
+
+        let document = Document::sign(
+            author,
+            String::new(),
+            None,
+            "es.5".to_owned(),
+            "/hello".to_owned(),
+            SystemTime::now(),
+            share,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+        assert!(document.verify());
+    }
+
+    #[test]
+    fn sign_without_secret_keys_is_rejected() {
+        let public_author: Identity = Identity::new("alice".to_owned(), None)
+            .unwrap()
+            .to_string()
+            .parse()
+            .unwrap();
+        let share = ShareAddress::new("garden".to_owned(), None).unwrap();
+
+        let result = Document::sign(
+            public_author,
+            String::new(),
+            None,
+            "es.5".to_owned(),
+            "/hello".to_owned(),
+            SystemTime::now(),
+            share,
+            None,
+            None,
+            None,
+            None,
+        );
+
+        assert!(matches!(result, Err(DocumentError::MissingSecretKey)));
+    }
+
+    #[test]
+    fn serialize_then_parse_round_trips() {
+        let author = Identity::new("alice".to_owned(), None).unwrap();
+        let share = ShareAddress::new("garden".to_owned(), None).unwrap();
+
+        let document = Document::sign(
+            author,
+            String::new(),
+            None,
+            "es.5".to_owned(),
+            "/hello".to_owned(),
+            SystemTime::now(),
+            share,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let parsed = Document::parse(&document.serialize()).unwrap();
+        assert_eq!(parsed.text, document.text);
+        assert_eq!(parsed.signature, document.signature);
+        assert!(parsed.verify());
+    }
+
+    #[test]
+    fn text_with_control_characters_is_rejected() {
+        let author = Identity::new("alice".to_owned(), None).unwrap();
+        let share = ShareAddress::new("garden".to_owned(), None).unwrap();
+
+        let result = Document::sign(
+            author,
+            "hello\tworld".to_owned(),
+            None,
+            "es.5".to_owned(),
+            "/hello".to_owned(),
+            SystemTime::now(),
+            share,
+            None,
+            None,
+            None,
+            None,
+        );
+
+        assert!(matches!(result, Err(DocumentError::InvalidText)));
+    }
+
+    #[test]
+    fn delegated_write_via_capability_validates() {
+        let alice = Identity::new("alice".to_owned(), None).unwrap();
+        let bob = Identity::new("bob".to_owned(), None).unwrap();
+        let share = ShareAddress::new("garden".to_owned(), None).unwrap();
+
+        let root = WriteCapability::new(
+            &alice,
+            bob.public,
+            "/~@alice/public".to_owned(),
+            SystemTime::now() + Duration::from_secs(3600),
+            None,
+        )
+        .unwrap();
+
+        let document = Document::sign(
+            bob,
+            String::new(),
+            None,
+            "es.5".to_owned(),
+            "/~@alice/public/notes".to_owned(),
+            SystemTime::now(),
+            share,
+            None,
+            None,
+            None,
+            Some(vec![root]),
+        )
+        .unwrap();
+
+        assert!(document.verify());
+        assert!(document.validate_path());
+    }
+
+    #[test]
+    fn serialize_then_parse_round_trips_capabilities() {
+        let alice = Identity::new("alice".to_owned(), None).unwrap();
+        let bob = Identity::new("bob".to_owned(), None).unwrap();
+        let share = ShareAddress::new("garden".to_owned(), None).unwrap();
+
+        let root = WriteCapability::new(
+            &alice,
+            bob.public,
+            "/~@alice/public".to_owned(),
+            SystemTime::now() + Duration::from_secs(3600),
+            None,
+        )
+        .unwrap();
+
+        let document = Document::sign(
+            bob,
+            String::new(),
+            None,
+            "es.5".to_owned(),
+            "/~@alice/public/notes".to_owned(),
+            SystemTime::now(),
+            share,
+            None,
+            None,
+            None,
+            Some(vec![root]),
+        )
+        .unwrap();
+
+        let parsed = Document::parse(&document.serialize()).unwrap();
+        assert_eq!(parsed.capabilities.len(), 1);
+        assert_eq!(parsed.capabilities[0].path_prefix, "/~@alice/public");
+        assert!(parsed.verify());
+        assert!(parsed.validate_path());
     }
 }
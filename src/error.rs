@@ -12,6 +12,15 @@ pub enum ShareAddressError {
     #[error("Share name cannot start with a digit.")]
     #[diagnostic(code(share_address::name::starts_with_digit))]
     StartsWithDigit,
+    #[error("Share address is missing its `+' sigil.")]
+    #[diagnostic(code(share_address::parse::missing_sigil))]
+    MissingSigil,
+    #[error("Share address public key is not valid Base32 (RFC 4648, no padding).")]
+    #[diagnostic(code(share_address::parse::invalid_base32))]
+    InvalidBase32,
+    #[error("Share address public key must decode to exactly 32 bytes forming a valid Ed25519 public key.")]
+    #[diagnostic(code(share_address::parse::invalid_key_length))]
+    InvalidKeyLength,
 }
 
 #[derive(Error, Diagnostic, Debug)]
@@ -25,11 +34,20 @@ pub enum IdentityError {
     #[error("Identity shortname cannot start with a digit.")]
     #[diagnostic(code(identity::name::starts_with_digit))]
     StartsWithDigit,
+    #[error("Identity address is missing its `@' sigil.")]
+    #[diagnostic(code(identity::parse::missing_sigil))]
+    MissingSigil,
+    #[error("Identity address public key is not valid Base32 (RFC 4648, no padding).")]
+    #[diagnostic(code(identity::parse::invalid_base32))]
+    InvalidBase32,
+    #[error("Identity address public key must decode to exactly 32 bytes forming a valid Ed25519 public key.")]
+    #[diagnostic(code(identity::parse::invalid_key_length))]
+    InvalidKeyLength,
 }
 
 #[derive(Error, Diagnostic, Debug)]
 pub enum DocumentError {
-    #[error("Document text must be less than or equal to 8 000 bytes, and cannot be empty if an attachment is present.")]
+    #[error("Document text must be less than or equal to 8 000 bytes, must not contain ASCII control characters, and cannot be empty if an attachment is present.")]
     #[diagnostic(code(document::invalid_text))]
     InvalidText,
     #[error("Document text hash must be a valid SHA-256 hash of the document text, encoded in Base32 (RFC 4648, no padding) with a leading `b'.")]
@@ -59,4 +77,23 @@ pub enum DocumentError {
     #[error("Document attachment hash must be a valid SHA-256 hash of the attachment, encoded in Base32 (RFC 4648, no padding) with a leading `b', with a total length of 53 characters.")]
     #[diagnostic(code(document::invalid_attachment_hash))]
     InvalidAttachmentHash,
+    #[error("Failed to read document attachment.")]
+    #[diagnostic(code(document::attachment_read_failed))]
+    AttachmentReadFailed(#[from] std::io::Error),
+    #[error("Document is not valid tag/value wire format: each line must be `key\\tvalue', with no unknown or duplicate keys, and every required key present.")]
+    #[diagnostic(code(document::malformed))]
+    MalformedDocument,
+    #[error("Signing a document locally requires both the author and the share to hold their secret keys.")]
+    #[diagnostic(code(document::missing_secret_key))]
+    MissingSecretKey,
+}
+
+#[derive(Error, Diagnostic, Debug)]
+pub enum CapabilityError {
+    #[error("A write capability can only be issued by an identity holding its own secret key.")]
+    #[diagnostic(code(capability::missing_secret_key))]
+    MissingSecretKey,
+    #[error("Write capability is not valid `encode()` wire format: each link must have all six comma-separated fields, with no unknown trailing fields.")]
+    #[diagnostic(code(capability::malformed))]
+    Malformed,
 }
@@ -0,0 +1,368 @@
+use crate::document::Document;
+use data_encoding::BASE32_NOPAD;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+/// Describes a document format version: its signature shape, its hash
+/// algorithm, the canonical field set and ordering hashed by
+/// `Document::hash_document`, and the per-field validation rules.
+///
+/// `Document` dispatches through a registered `DocumentFormat` rather than
+/// branching on the `format` string directly, so new format versions (or an
+/// entirely custom format) can be added without editing `Document` itself.
+/// Every method except `name` has a default implementation matching the
+/// `es.5` rules, apart from `signature_length`, whose default leaves the
+/// signature length unconstrained; override only what a new format changes.
+pub trait DocumentFormat: Send + Sync {
+    /// The format's own identifier, e.g. `"es.5"`.
+    fn name(&self) -> &str;
+
+    /// The required Base32 signature length (including the leading `b`),
+    /// or `None` if this format does not constrain signature length.
+    fn signature_length(&self) -> Option<usize> {
+        None
+    }
+
+    /// Hashes canonical `key\tvalue\n` field lines, returning a `b`-prefixed
+    /// Base32 digest.
+    fn hash(&self, canonical_fields: &[u8]) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(canonical_fields);
+        format!("b{}", BASE32_NOPAD.encode(&hasher.finalize()))
+    }
+
+    /// The canonical, ordered fields hashed by `hash_document` and (de)serialized
+    /// on the wire. Neither `signature` nor `share_signature` is included,
+    /// since both are signatures *over* this hash and so can't be part of
+    /// the input to it.
+    fn canonical_fields(&self, document: &Document) -> Vec<(&'static str, String)> {
+        let mut fields = Vec::new();
+        if document.attachment_hash.is_some() && document.attachment_size.is_some() {
+            fields.push((
+                "attachment_hash",
+                document.attachment_hash.clone().unwrap_or_default(),
+            ));
+            fields.push((
+                "attachment_size",
+                document.attachment_size.unwrap_or_default().to_string(),
+            ));
+        }
+        fields.push(("author", document.author.to_string()));
+        if !document.capabilities.is_empty() {
+            fields.push(("capabilities", document.encode_capabilities()));
+        }
+        fields.push(("delete_after", document.delete_after_as_u128().to_string()));
+        fields.push(("format", document.format.clone()));
+        fields.push(("path", document.path.clone()));
+        fields.push(("share", document.share.to_string()));
+        fields.push(("text_hash", document.text_hash.clone()));
+        fields.push(("timestamp", document.timestamp_as_u128().to_string()));
+        fields
+    }
+
+    fn validate_text(&self, document: &Document) -> bool {
+        document.text.as_bytes().len() <= 8000
+            && !document.text.contains(|c: char| c.is_ascii_control())
+            && (document.attachment_hash.is_some()
+                && document.attachment_size.is_some()
+                && !document.text.is_empty()
+                || document.attachment_hash.is_none()
+                    && document.attachment_size.is_none()
+                    && document.text.is_empty())
+    }
+
+    fn validate_text_hash(&self, document: &Document) -> bool {
+        document.text_hash.chars().nth(0).unwrap_or_default() == 'b'
+            && document.text_hash.len() == 53
+            && BASE32_NOPAD
+                .decode(document.text_hash[1..].as_bytes())
+                .is_ok()
+            && document.text_hash == Document::hash_text(document.text.clone())
+    }
+
+    fn validate_timestamp(&self, document: &Document) -> bool {
+        let timestamp_int = document.timestamp_as_u128();
+        timestamp_int >= (10_u128).pow(13) && timestamp_int <= (2_u128).pow(53) - 2
+    }
+
+    fn validate_delete_after(&self, document: &Document) -> bool {
+        document
+            .delete_after
+            .map(|delete_after| {
+                let delete_after_int = document.delete_after_as_u128();
+                delete_after_int >= (10_u128).pow(13)
+                    && delete_after_int <= (2_u128).pow(53) - 2
+                    && delete_after > document.timestamp
+            })
+            // `delete_after` is optional: a document without one is trivially valid.
+            .unwrap_or(true)
+    }
+
+    fn validate_path(&self, document: &Document) -> bool {
+        document.path.is_ascii()
+            && !document.path.contains(|c: char| c.is_ascii_whitespace())
+            && !document.path.contains(|c: char| c.is_ascii_control())
+            && document.path.len() >= 2
+            && document.path.len() <= 512
+            && document.path.chars().nth(0).unwrap_or_default() == '/'
+            && &document.path[0..2] != "/@"
+            && !document.path.contains("//")
+            && ((document.delete_after.is_none() && !document.path.contains('!'))
+                || (document.delete_after.is_some() && document.path.contains('!')))
+            && !document.path.contains('?')
+            && !document.path.contains('#')
+            && !document.path.contains(';')
+            && !document.path.contains('<')
+            && !document.path.contains('>')
+            && !document.path.contains('"')
+            && !document.path.contains('[')
+            && !document.path.contains('\\')
+            && !document.path.contains(']')
+            && !document.path.contains('^')
+            && !document.path.contains('{')
+            && !document.path.contains('|')
+            && !document.path.contains('}')
+            && (document.attachment_hash.is_some()
+                && document.attachment_size.is_some()
+                && Path::new(&document.path).extension().is_some()
+                || document.attachment_hash.is_none()
+                    && document.attachment_size.is_none()
+                    && Path::new(&document.path).extension().is_none())
+            && (!document.path.contains('~')
+                || document
+                    .path
+                    .contains(&("~@".to_owned() + &document.author.shortname))
+                || document.capabilities.iter().any(|capability| {
+                    capability.path_prefix.starts_with("/~@")
+                        && document.path.starts_with(capability.path_prefix.as_str())
+                        && capability.verify_chain(
+                            &document.author.public,
+                            &document.path,
+                            document.timestamp,
+                        )
+                }))
+    }
+}
+
+/// The `es.5` format: SHA-256 hashing, 104-character Ed25519 signatures, and
+/// the full field set including attachments. This is the format the crate
+/// has always spoken; every `DocumentFormat` default except
+/// `signature_length` mirrors it.
+pub struct Es5Format;
+
+impl DocumentFormat for Es5Format {
+    fn name(&self) -> &str {
+        "es.5"
+    }
+
+    fn signature_length(&self) -> Option<usize> {
+        Some(104)
+    }
+}
+
+/// The `es.4` format, the predecessor to `es.5`: the same SHA-256 hashing
+/// and canonical ordering, but with no support for attachments, no fixed
+/// signature length, and a hash frozen to its original field set: an
+/// `es.4` document's hash never commits to `capabilities`, although its
+/// path validation (the shared default) still accepts capability-based
+/// writes, so capability-based writes should only be trusted under `es.5`
+/// or later.
+pub struct Es4Format;
+
+impl DocumentFormat for Es4Format {
+    fn name(&self) -> &str {
+        "es.4"
+    }
+
+    fn canonical_fields(&self, document: &Document) -> Vec<(&'static str, String)> {
+        vec![
+            ("author", document.author.to_string()),
+            ("delete_after", document.delete_after_as_u128().to_string()),
+            ("format", document.format.clone()),
+            ("path", document.path.clone()),
+            ("share", document.share.to_string()),
+            ("text_hash", document.text_hash.clone()),
+            ("timestamp", document.timestamp_as_u128().to_string()),
+        ]
+    }
+
+    fn validate_text(&self, document: &Document) -> bool {
+        document.attachment_hash.is_none()
+            && document.attachment_size.is_none()
+            && document.text.as_bytes().len() <= 8000
+            && !document.text.contains(|c: char| c.is_ascii_control())
+    }
+}
+
+fn registry() -> &'static Mutex<HashMap<String, Arc<dyn DocumentFormat>>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<String, Arc<dyn DocumentFormat>>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let mut formats: HashMap<String, Arc<dyn DocumentFormat>> = HashMap::new();
+        formats.insert("es.4".to_owned(), Arc::new(Es4Format));
+        formats.insert("es.5".to_owned(), Arc::new(Es5Format));
+        Mutex::new(formats)
+    })
+}
+
+/// Registers a `DocumentFormat`, making it available to `Document` under
+/// its own `name()`. Overwrites any existing format registered under the
+/// same name, so a caller can also use this to replace `es.4`/`es.5` with a
+/// customized implementation.
+pub fn register_format(format: Arc<dyn DocumentFormat>) {
+    registry()
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .insert(format.name().to_owned(), format);
+}
+
+/// Looks up a registered `DocumentFormat` by name.
+pub fn get_format(name: &str) -> Option<Arc<dyn DocumentFormat>> {
+    registry()
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .get(name)
+        .cloned()
+}
+
+/// The format `Document` falls back to for an unregistered `format` string,
+/// so documents in an unknown format are still hashed and validated
+/// consistently rather than rejected outright.
+pub fn default_format() -> Arc<dyn DocumentFormat> {
+    Arc::new(Es5Format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::address::{Identity, ShareAddress};
+    use crate::error::DocumentError;
+    use std::time::SystemTime;
+
+    #[test]
+    fn es4_format_signs_and_validates() {
+        let author = Identity::new("alice".to_owned(), None).unwrap();
+        let share = ShareAddress::new("garden".to_owned(), None).unwrap();
+
+        let document = Document::sign(
+            author,
+            String::new(),
+            None,
+            "es.4".to_owned(),
+            "/hello".to_owned(),
+            SystemTime::now(),
+            share,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+        assert!(document.verify());
+    }
+
+    /// A format that hashes with a lowercased digest (the default digest is
+    /// uppercase Base32), used only to prove `Document` dispatches through
+    /// the registry rather than hardcoding `es.4`/`es.5` behavior.
+    struct LowercaseHashFormat;
+
+    impl DocumentFormat for LowercaseHashFormat {
+        fn name(&self) -> &str {
+            "es.5-test-lowercase"
+        }
+
+        fn hash(&self, canonical_fields: &[u8]) -> String {
+            let mut hasher = Sha256::new();
+            hasher.update(canonical_fields);
+            format!(
+                "b{}",
+                BASE32_NOPAD.encode(&hasher.finalize()).to_lowercase()
+            )
+        }
+    }
+
+    #[test]
+    fn custom_registered_format_is_dispatched_to() {
+        register_format(Arc::new(LowercaseHashFormat));
+
+        let author = Identity::new("alice".to_owned(), None).unwrap();
+        let share = ShareAddress::new("garden".to_owned(), None).unwrap();
+
+        let document = Document::sign(
+            author,
+            String::new(),
+            None,
+            "es.5-test-lowercase".to_owned(),
+            "/hello".to_owned(),
+            SystemTime::now(),
+            share,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+        assert!(document.verify());
+        assert!(document.hash_document()[1..].chars().all(|c| !c.is_uppercase()));
+    }
+
+    #[test]
+    fn register_format_overrides_an_existing_entry() {
+        struct AcceptsEverything;
+        impl DocumentFormat for AcceptsEverything {
+            fn name(&self) -> &str {
+                "es.5-test-override"
+            }
+        }
+        struct RejectsText;
+        impl DocumentFormat for RejectsText {
+            fn name(&self) -> &str {
+                "es.5-test-override"
+            }
+            fn validate_text(&self, _document: &Document) -> bool {
+                false
+            }
+        }
+
+        register_format(Arc::new(AcceptsEverything));
+        let author = Identity::new("alice".to_owned(), None).unwrap();
+        let share = ShareAddress::new("garden".to_owned(), None).unwrap();
+        assert!(Document::sign(
+            author,
+            String::new(),
+            None,
+            "es.5-test-override".to_owned(),
+            "/hello".to_owned(),
+            SystemTime::now(),
+            share,
+            None,
+            None,
+            None,
+            None,
+        )
+        .is_ok());
+
+        register_format(Arc::new(RejectsText));
+        let author = Identity::new("bob".to_owned(), None).unwrap();
+        let share = ShareAddress::new("meadow".to_owned(), None).unwrap();
+        let result = Document::sign(
+            author,
+            String::new(),
+            None,
+            "es.5-test-override".to_owned(),
+            "/hello".to_owned(),
+            SystemTime::now(),
+            share,
+            None,
+            None,
+            None,
+            None,
+        );
+        assert!(matches!(result, Err(DocumentError::InvalidText)));
+    }
+}